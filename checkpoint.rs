@@ -1,10 +1,13 @@
 use ink::primitives::AccountId;
 use ink::storage::Mapping;
 
+/// Storage layout for a collection at `storage_version` 1.
 #[ink::storage_item]
-pub struct CheckpointData {
+pub struct CheckpointDataV1 {
     account_to_checkpoint_map: Mapping<(AccountId, u128), Checkpoint>,
     num_of_checkpoints_per_account: Mapping<AccountId, u128>,
+    total_supply_checkpoints: Mapping<u128, Checkpoint>,
+    num_of_total_supply_checkpoints: u128,
 }
 
 #[derive(Debug, Clone, scale::Encode, scale::Decode)]
@@ -17,11 +20,13 @@ pub struct Checkpoint {
     pub votes: u32,
 }
 
-impl CheckpointData {
+impl CheckpointDataV1 {
     pub fn new() -> Self {
-        CheckpointData {
+        CheckpointDataV1 {
             account_to_checkpoint_map: Mapping::new(),
             num_of_checkpoints_per_account: Mapping::new(),
+            total_supply_checkpoints: Mapping::new(),
+            num_of_total_supply_checkpoints: 0,
         }
     }
 
@@ -102,6 +107,7 @@ impl CheckpointData {
         &mut self,
         account: AccountId,
         increment: bool,
+        amount: u32,
         current_block: u32,
     ) {
         let num_of_checkpoints = self
@@ -114,7 +120,7 @@ impl CheckpointData {
                 (account, 0),
                 &Checkpoint {
                     from_block: current_block,
-                    votes: 1,
+                    votes: if increment { amount } else { 0 },
                 },
             );
 
@@ -129,21 +135,177 @@ impl CheckpointData {
                 .unwrap();
 
             let next_cp_votes = if increment {
-                last_checkpoint.votes + 1
+                last_checkpoint.votes.saturating_add(amount)
             } else {
-                last_checkpoint.votes - 1
+                last_checkpoint.votes.saturating_sub(amount)
             };
 
-            self.account_to_checkpoint_map.insert(
-                (account, num_of_checkpoints),
+            if last_checkpoint.from_block == current_block {
+                // several changes in the same block: overwrite the last entry
+                // instead of growing the series with duplicate `from_block`s
+                self.account_to_checkpoint_map.insert(
+                    (account, last_checkpoint_idx),
+                    &Checkpoint {
+                        from_block: current_block,
+                        votes: next_cp_votes,
+                    },
+                );
+            } else {
+                self.account_to_checkpoint_map.insert(
+                    (account, num_of_checkpoints),
+                    &Checkpoint {
+                        from_block: current_block,
+                        votes: next_cp_votes,
+                    },
+                );
+
+                self.num_of_checkpoints_per_account
+                    .insert(account, &(num_of_checkpoints + 1));
+            }
+        }
+    }
+
+    pub fn get_last_total_supply_checkpoint(&self) -> Option<Checkpoint> {
+        if self.num_of_total_supply_checkpoints == 0 {
+            return None;
+        }
+
+        let last_checkpoint_idx = self.num_of_total_supply_checkpoints - 1;
+        self.total_supply_checkpoints.get(last_checkpoint_idx)
+    }
+
+    pub fn get_total_supply_checkpoint_at_block(
+        &self,
+        wanted_block: u32,
+        current_block: u32,
+    ) -> Option<Checkpoint> {
+        if wanted_block > current_block {
+            return None;
+        }
+
+        let num_checkpoints = self.num_of_total_supply_checkpoints;
+        if num_checkpoints == 0 {
+            return None;
+        }
+
+        if self
+            .total_supply_checkpoints
+            .get(0)
+            .unwrap()
+            .from_block
+            > wanted_block
+        {
+            return None;
+        }
+
+        let mut lower = 0;
+        let mut upper = num_checkpoints - 1;
+
+        while upper > lower {
+            let center = upper - (upper - lower) / 2;
+            // TODO handle error
+            let cp = self.total_supply_checkpoints.get(center).unwrap();
+
+            if cp.from_block == wanted_block {
+                return Some(cp);
+            } else if cp.from_block < wanted_block {
+                lower = center;
+            } else {
+                upper = center - 1;
+            }
+        }
+
+        // TODO handle error
+        return Some(self.total_supply_checkpoints.get(lower).unwrap());
+    }
+
+    pub fn add_new_total_supply_checkpoint(&mut self, increment: bool, current_block: u32) {
+        let num_of_checkpoints = self.num_of_total_supply_checkpoints;
+
+        if num_of_checkpoints == 0 {
+            self.total_supply_checkpoints.insert(
+                0,
                 &Checkpoint {
                     from_block: current_block,
-                    votes: next_cp_votes,
+                    votes: if increment { 1 } else { 0 },
                 },
             );
 
-            self.num_of_checkpoints_per_account
-                .insert(account, &(num_of_checkpoints + 1));
+            self.num_of_total_supply_checkpoints = 1;
+        } else {
+            let last_checkpoint_idx = num_of_checkpoints - 1;
+
+            // TODO handle error
+            let last_checkpoint = self
+                .total_supply_checkpoints
+                .get(last_checkpoint_idx)
+                .unwrap();
+
+            let next_cp_votes = if increment {
+                last_checkpoint.votes.saturating_add(1)
+            } else {
+                last_checkpoint.votes.saturating_sub(1)
+            };
+
+            if last_checkpoint.from_block == current_block {
+                self.total_supply_checkpoints.insert(
+                    last_checkpoint_idx,
+                    &Checkpoint {
+                        from_block: current_block,
+                        votes: next_cp_votes,
+                    },
+                );
+            } else {
+                self.total_supply_checkpoints.insert(
+                    num_of_checkpoints,
+                    &Checkpoint {
+                        from_block: current_block,
+                        votes: next_cp_votes,
+                    },
+                );
+
+                self.num_of_total_supply_checkpoints = num_of_checkpoints + 1;
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(byte: u8) -> AccountId {
+        AccountId::from([byte; 32])
+    }
+
+    #[ink::test]
+    fn same_block_changes_coalesce_into_one_checkpoint() {
+        let mut checkpoints = CheckpointDataV1::new();
+        let alice = account(1);
+
+        checkpoints.add_new_checkpoint_to_account(alice, true, 1, 10);
+        checkpoints.add_new_checkpoint_to_account(alice, true, 2, 10);
+        checkpoints.add_new_checkpoint_to_account(alice, false, 1, 10);
+
+        assert_eq!(checkpoints.num_of_checkpoints_per_account.get(alice), Some(1));
+        let last = checkpoints.get_last_checkpoint(alice).unwrap();
+        assert_eq!(last.from_block, 10);
+        assert_eq!(last.votes, 2);
+    }
+
+    #[ink::test]
+    fn decrementing_an_account_already_at_zero_saturates_instead_of_panicking() {
+        let mut checkpoints = CheckpointDataV1::new();
+        let alice = account(1);
+
+        checkpoints.add_new_checkpoint_to_account(alice, false, 1, 10);
+        let first = checkpoints.get_last_checkpoint(alice).unwrap();
+        assert_eq!(first.votes, 0);
+
+        // a later block so this is a genuinely new checkpoint, not a coalesce
+        checkpoints.add_new_checkpoint_to_account(alice, false, 1, 11);
+        let second = checkpoints.get_last_checkpoint(alice).unwrap();
+        assert_eq!(second.from_block, 11);
+        assert_eq!(second.votes, 0);
+    }
+}
@@ -6,18 +6,26 @@ pub use crate::aaw::AawRef;
 
 #[ink::contract]
 mod aaw {
-    use crate::checkpoint::CheckpointData;
+    use crate::checkpoint::CheckpointDataV1;
     use ink::prelude::{string::String, vec, vec::Vec};
+    use ink::storage::Mapping;
     use psp34::{
-        types::Id, PSP34Data, PSP34Enumerable, PSP34Error, PSP34Event, PSP34Metadata,
-        PSP34Mintable, PSP34,
+        types::Id, PSP34Burnable, PSP34Data, PSP34Enumerable, PSP34Error, PSP34Event,
+        PSP34Metadata, PSP34Mintable, PSP34,
     };
 
+    /// The `storage_version` of a freshly deployed collection. Bumped whenever
+    /// `CheckpointDataV1` is superseded by a new layout, so code upgraded via
+    /// `set_code_hash` can tell which layout a given instance's storage is in.
+    const CURRENT_STORAGE_VERSION: u16 = 1;
+
     #[ink(storage)]
     pub struct Aaw {
         psp34: PSP34Data,
         owner: AccountId,
-        checkpoints: CheckpointData,
+        checkpoints: CheckpointDataV1,
+        delegates: Mapping<AccountId, AccountId>,
+        storage_version: u16,
     }
 
     impl Aaw {
@@ -26,8 +34,34 @@ mod aaw {
             Self {
                 psp34: PSP34Data::new(),
                 owner: Self::env().caller(),
-                checkpoints: CheckpointData::new(),
+                checkpoints: CheckpointDataV1::new(),
+                delegates: Mapping::new(),
+                storage_version: CURRENT_STORAGE_VERSION,
+            }
+        }
+
+        /// The `storage_version` this instance's storage is laid out as. Code
+        /// upgraded in via `set_code_hash` reads this to decide whether a
+        /// migration is needed before using `checkpoints`.
+        #[ink(message)]
+        pub fn get_storage_version(&self) -> u16 {
+            self.storage_version
+        }
+
+        /// Upgrades the contract's code while keeping its storage, gated to the
+        /// contract owner.
+        #[ink(message)]
+        pub fn set_code_hash(&mut self, code_hash: Hash) -> Result<(), PSP34Error> {
+            if self.env().caller() != self.owner {
+                return Err(PSP34Error::Custom(String::from(
+                    "this message is only callable by the owner of the contract",
+                )));
             }
+
+            self.env()
+                .set_code_hash(&code_hash)
+                .map_err(|_| PSP34Error::Custom(String::from("failed to set new code hash")))?;
+            Ok(())
         }
 
         #[ink(message)]
@@ -45,6 +79,78 @@ mod aaw {
                 .map_or(0, |c| c.votes)
         }
 
+        #[ink(message)]
+        pub fn get_current_total_supply(&self) -> u32 {
+            self.checkpoints
+                .get_last_total_supply_checkpoint()
+                .map_or(0, |c| c.votes)
+        }
+
+        #[ink(message)]
+        pub fn get_past_total_supply(&self, block: BlockNumber) -> u32 {
+            let current_block = self.env().block_number();
+            self.checkpoints
+                .get_total_supply_checkpoint_at_block(block, current_block)
+                .map_or(0, |c| c.votes)
+        }
+
+        #[ink(message)]
+        pub fn get_delegate(&self, account: AccountId) -> AccountId {
+            self.delegates
+                .get(account)
+                .unwrap_or(AccountId::from([0u8; 32]))
+        }
+
+        #[ink(message)]
+        pub fn delegate(&mut self, delegatee: AccountId) {
+            let delegator = self.env().caller();
+            let current_delegate = self.get_delegate(delegator);
+            let delegator_balance = self.psp34.balance_of(delegator);
+
+            self.delegates.insert(delegator, &delegatee);
+
+            self.env().emit_event(DelegateChanged {
+                delegator,
+                from: current_delegate,
+                to: delegatee,
+            });
+
+            self.move_delegates(current_delegate, delegatee, delegator_balance);
+        }
+
+        fn move_delegates(&mut self, src_rep: AccountId, dst_rep: AccountId, amount: u32) {
+            let zero_account = AccountId::from([0u8; 32]);
+            let current_block = self.env().block_number();
+
+            if src_rep == dst_rep || amount == 0 {
+                return;
+            }
+
+            if src_rep != zero_account {
+                let previous = self.get_current_votes(src_rep);
+                self.checkpoints
+                    .add_new_checkpoint_to_account(src_rep, false, amount, current_block);
+                let new = self.get_current_votes(src_rep);
+                self.env().emit_event(DelegateVotesChanged {
+                    delegate: src_rep,
+                    previous,
+                    new,
+                });
+            }
+
+            if dst_rep != zero_account {
+                let previous = self.get_current_votes(dst_rep);
+                self.checkpoints
+                    .add_new_checkpoint_to_account(dst_rep, true, amount, current_block);
+                let new = self.get_current_votes(dst_rep);
+                self.env().emit_event(DelegateVotesChanged {
+                    delegate: dst_rep,
+                    previous,
+                    new,
+                });
+            }
+        }
+
         fn emit_events(&self, events: Vec<PSP34Event>) {
             for event in events {
                 match event {
@@ -92,6 +198,20 @@ mod aaw {
         data: Vec<u8>,
     }
 
+    #[ink(event)]
+    pub struct DelegateChanged {
+        delegator: AccountId,
+        from: AccountId,
+        to: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct DelegateVotesChanged {
+        delegate: AccountId,
+        previous: u32,
+        new: u32,
+    }
+
     impl PSP34 for Aaw {
         #[ink(message)]
         fn collection_id(&self) -> Id {
@@ -132,13 +252,9 @@ mod aaw {
         #[ink(message)]
         fn transfer(&mut self, to: AccountId, id: Id, data: Vec<u8>) -> Result<(), PSP34Error> {
             let from = self.env().caller();
-            let current_block = self.env().block_number();
             let events = self.psp34.transfer(from, to, id, data)?;
 
-            self.checkpoints
-                .add_new_checkpoint_to_account(from, false, current_block);
-            self.checkpoints
-                .add_new_checkpoint_to_account(to, true, current_block);
+            self.move_delegates(self.get_delegate(from), self.get_delegate(to), 1);
             self.emit_events(events);
             Ok(())
         }
@@ -151,12 +267,8 @@ mod aaw {
             id: Id,
             data: Vec<u8>,
         ) -> Result<(), PSP34Error> {
-            let current_block = self.env().block_number();
             let events = self.psp34.transfer_from(from, to, id, data)?;
-            self.checkpoints
-                .add_new_checkpoint_to_account(from, false, current_block);
-            self.checkpoints
-                .add_new_checkpoint_to_account(to, true, current_block);
+            self.move_delegates(self.get_delegate(from), self.get_delegate(to), 1);
             self.emit_events(events);
             Ok(())
         }
@@ -179,17 +291,41 @@ mod aaw {
             account: AccountId,
             attributes: Vec<(Vec<u8>, Vec<u8>)>,
         ) -> Result<(), PSP34Error> {
-            let current_block = self.env().block_number();
-
             if self.env().caller() != self.owner {
                 return Err(PSP34Error::Custom(String::from(
                     "this message is only callable by the owner of the contract",
                 )));
             }
 
+            let current_block = self.env().block_number();
             let events = self.psp34.mint_with_attributes(account, attributes)?;
             self.checkpoints
-                .add_new_checkpoint_to_account(account, true, current_block);
+                .add_new_total_supply_checkpoint(true, current_block);
+            self.move_delegates(AccountId::from([0u8; 32]), self.get_delegate(account), 1);
+            self.emit_events(events);
+            Ok(())
+        }
+    }
+
+    impl PSP34Burnable for Aaw {
+        #[ink(message)]
+        fn burn(&mut self, account: AccountId, id: Id) -> Result<(), PSP34Error> {
+            let caller = self.env().caller();
+            let current_block = self.env().block_number();
+
+            if caller != account
+                && !self.psp34.allowance(account, caller, Some(id.clone()))
+                && !self.psp34.allowance(account, caller, None)
+            {
+                return Err(PSP34Error::Custom(String::from(
+                    "caller is not the token owner nor an approved operator",
+                )));
+            }
+
+            let events = self.psp34.burn(caller, account, id)?;
+            self.checkpoints
+                .add_new_total_supply_checkpoint(false, current_block);
+            self.move_delegates(self.get_delegate(account), AccountId::from([0u8; 32]), 1);
             self.emit_events(events);
             Ok(())
         }